@@ -0,0 +1,22 @@
+use color_eyre::Result;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+use {package_name}::\{part1, part2};
+
+#[derive(StructOpt, Debug)]
+struct Opt \{
+    /// Path to the puzzle input file.
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+fn main() -> Result<()> \{
+    color_eyre::install()?;
+    let opt = Opt::from_args();
+
+    part1(&opt.input)?;
+    part2(&opt.input)?;
+
+    Ok(())
+}
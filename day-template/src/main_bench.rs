@@ -0,0 +1,39 @@
+use color_eyre::Result;
+use std::path::\{Path, PathBuf};
+use std::time::Instant;
+use structopt::StructOpt;
+
+use {package_name}::\{part1, part2};
+
+#[derive(StructOpt, Debug)]
+struct Opt \{
+    /// Path to the real puzzle input file.
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+
+    /// Path to an example input to run the same parts against first, for comparison.
+    #[structopt(long, parse(from_os_str))]
+    example: Option<PathBuf>,
+}
+
+fn timed(label: &str, input: &Path, part: impl Fn(&Path) -> Result<()>) -> Result<()> \{
+    let start = Instant::now();
+    part(input)?;
+    println!("\{} (\{:?}): \{:?}", label, input, start.elapsed());
+    Ok(())
+}
+
+fn main() -> Result<()> \{
+    color_eyre::install()?;
+    let opt = Opt::from_args();
+
+    if let Some(example) = &opt.example \{
+        timed("part1 example", example, part1)?;
+        timed("part2 example", example, part2)?;
+    }
+
+    timed("part1", &opt.input, part1)?;
+    timed("part2", &opt.input, part2)?;
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
-use serde::Serialize;
-use std::{io::Write, path::{Path, PathBuf}};
+use include_dir::{include_dir, Dir};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io::Write, path::{Path, PathBuf}};
 use std::str::FromStr;
 use structopt::StructOpt;
 use thiserror::Error;
@@ -8,7 +9,32 @@ use toml_edit::Document;
 
 use aoclib::config::Config;
 
-const TEMPLATE_FILES: &[&str] = &["Cargo.toml", "src/lib.rs", "src/main.rs"];
+const TEMPLATE_FILES: &[&str] = &[
+    "Cargo.toml",
+    "src/lib.rs",
+    "src/main.rs",
+    "src/main_bench.rs",
+    TEMPLATE_MANIFEST_FILE,
+];
+
+/// Template used in place of `src/main.rs` when `--with-bench` is passed: a runner that times
+/// each part and can optionally run against an example input before the real one.
+const BENCH_MAIN_TEMPLATE: &str = "src/main_bench.rs";
+
+/// `TEMPLATE_FILES` entries that postdate the historical upstream template set. A `--template-source`
+/// pointed at that upstream (or any other template set predating them) won't have these; a missing
+/// one is not an error.
+const OPTIONAL_TEMPLATE_FILES: &[&str] = &[TEMPLATE_MANIFEST_FILE, BENCH_MAIN_TEMPLATE];
+
+/// Name of the per-template-directory manifest declaring its variables and rendered file list.
+const TEMPLATE_MANIFEST_FILE: &str = "template.toml";
+
+/// The canonical day templates, embedded into the binary at compile time.
+///
+/// These back `ensure_template_dir`'s default, offline behavior: a freshly configured year gets
+/// its template directory seeded from this copy rather than reaching out to GitHub. Passing
+/// `--template-source` opts back into fetching templates from an arbitrary URL instead.
+static DEFAULT_DAY_TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/day-template");
 
 /// Get `Cargo.toml` of the implementation directory.
 ///
@@ -65,75 +91,328 @@ fn add_crate_to_workspace(
 }
 
 /// Ensure the template directory from the configuration exists and is initialized.
-fn ensure_template_dir(config: &Config, year: u32) -> Result<PathBuf, Error> {
+///
+/// By default, any of `TEMPLATE_FILES` missing from the year's template directory is seeded from
+/// the copy embedded in this binary, so initialization works with no network access. Passing
+/// `template_source` opts back into the old behavior of fetching each missing file from that base
+/// URL instead, for users who want to track a template set other than the embedded one.
+fn ensure_template_dir(
+    config: &Config,
+    year: u32,
+    template_source: Option<&str>,
+) -> Result<PathBuf, Error> {
     let template_dir = config.day_template(year);
     if !template_dir.exists() {
         std::fs::create_dir_all(&template_dir)?;
     }
     for template in TEMPLATE_FILES {
         let template_path = template_dir.join(template);
-        if !template_path.exists() {
-            let url = format!(
-                "https://raw.githubusercontent.com/coriolinus/aoctool/master/day-template/{}",
-                template
-            );
-            let client = reqwest::blocking::Client::builder()
-                .gzip(true)
-                .timeout(std::time::Duration::from_secs(5))
-                .build()
-                .map_err(Error::ClientBuilder)?;
-            let mut response = client
-                .get(&url)
-                .send()
-                .map_err(Error::RequestingInput)?
-                .error_for_status()
-                .map_err(Error::ResponseStatus)?;
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .create_new(true)
-                .open(template_path)?;
-            response.copy_to(&mut file).map_err(Error::Downloading)?;
+        if template_path.exists() {
+            continue;
+        }
+        if let Some(parent) = template_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        match template_source {
+            Some(base_url) => {
+                let url = format!("{}/{}", base_url.trim_end_matches('/'), template);
+                let client = reqwest::blocking::Client::builder()
+                    .gzip(true)
+                    .timeout(std::time::Duration::from_secs(5))
+                    .build()
+                    .map_err(Error::ClientBuilder)?;
+                let response = client.get(&url).send().map_err(Error::RequestingInput)?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND
+                    && OPTIONAL_TEMPLATE_FILES.contains(template)
+                {
+                    // this template set predates `template`; fine, it's optional
+                    continue;
+                }
+                let mut response = response.error_for_status().map_err(Error::ResponseStatus)?;
+                let mut file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(template_path)?;
+                response.copy_to(&mut file).map_err(Error::Downloading)?;
+            }
+            None => {
+                let embedded = DEFAULT_DAY_TEMPLATE
+                    .get_file(template)
+                    .ok_or_else(|| Error::MissingEmbeddedTemplate(template.to_string()))?;
+                std::fs::write(&template_path, embedded.contents())?;
+            }
         }
     }
     Ok(template_dir)
 }
 
+/// A template directory's `template.toml`: the files it renders, and any variables besides the
+/// built-in `day`/`package_name` that it wants prompted or supplied via `--define`.
+#[derive(Debug, Deserialize)]
+struct TemplateManifest {
+    files: Vec<String>,
+    #[serde(default)]
+    variables: Vec<TemplateVariable>,
+    /// Shell command lines, run in order (via `sh -c`) before templates render. May be a bare
+    /// command (`cargo fmt`) or invoke a script shipped alongside this manifest.
+    #[serde(default)]
+    pre: Vec<String>,
+    /// Shell command lines, run in order (via `sh -c`) after templates render.
+    #[serde(default)]
+    post: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TemplateVariable {
+    name: String,
+    prompt: String,
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: VariableKind,
+    #[serde(default)]
+    choices: Vec<String>,
+    #[serde(default)]
+    regex: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum VariableKind {
+    String,
+    Bool,
+    Choice,
+}
+
+impl Default for VariableKind {
+    fn default() -> Self {
+        VariableKind::String
+    }
+}
+
+/// Load the `template.toml` for a template directory, falling back to the historical fixed file
+/// list and no extra variables for template directories predating this manifest.
+fn load_template_manifest(template_dir: &Path) -> Result<TemplateManifest, Error> {
+    let manifest_path = template_dir.join(TEMPLATE_MANIFEST_FILE);
+    if !manifest_path.exists() {
+        return Ok(TemplateManifest {
+            files: TEMPLATE_FILES
+                .iter()
+                .filter(|&&file| file != TEMPLATE_MANIFEST_FILE && file != BENCH_MAIN_TEMPLATE)
+                .map(|file| file.to_string())
+                .collect(),
+            variables: Vec::new(),
+            pre: Vec::new(),
+            post: Vec::new(),
+        });
+    }
+    let manifest: TemplateManifest =
+        toml::from_str(&std::fs::read_to_string(&manifest_path)?).map_err(Error::ParseTemplateManifest)?;
+
+    for variable in &manifest.variables {
+        if variable.kind == VariableKind::Choice && variable.choices.is_empty() {
+            Err(Error::EmptyChoiceVariable(variable.name.clone()))?;
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Resolve every variable a template manifest declares, in declaration order: take the value
+/// supplied via `--define`, falling back to an interactive prompt, then validate it against the
+/// variable's `regex` if one is given.
+fn resolve_variables(
+    manifest: &TemplateManifest,
+    defines: &[(String, String)],
+) -> Result<BTreeMap<String, serde_json::Value>, Error> {
+    let mut values = BTreeMap::new();
+    for variable in &manifest.variables {
+        let raw = match defines.iter().find(|(name, _)| name == &variable.name) {
+            Some((_, value)) => value.clone(),
+            None => prompt_for_variable(variable)?,
+        };
+
+        if let Some(pattern) = &variable.regex {
+            let re = regex::Regex::new(pattern)
+                .map_err(|err| Error::InvalidVariableRegex(variable.name.clone(), err))?;
+            if !re.is_match(&raw) {
+                Err(Error::VariableValidation(variable.name.clone(), raw))?;
+            }
+        }
+
+        let value = match variable.kind {
+            VariableKind::Bool => serde_json::Value::Bool(raw.eq_ignore_ascii_case("true") || raw == "y"),
+            _ => serde_json::Value::String(raw),
+        };
+        values.insert(variable.name.clone(), value);
+    }
+    Ok(values)
+}
+
+fn prompt_for_variable(variable: &TemplateVariable) -> Result<String, Error> {
+    match variable.kind {
+        VariableKind::Bool => {
+            let default = variable
+                .default
+                .as_deref()
+                .map(|d| d.eq_ignore_ascii_case("true") || d == "y")
+                .unwrap_or_default();
+            let answer = dialoguer::Confirm::new()
+                .with_prompt(&variable.prompt)
+                .default(default)
+                .interact()?;
+            Ok(answer.to_string())
+        }
+        VariableKind::Choice => {
+            let default = variable
+                .default
+                .as_deref()
+                .and_then(|d| variable.choices.iter().position(|choice| choice == d))
+                .unwrap_or_default();
+            let selection = dialoguer::Select::new()
+                .with_prompt(&variable.prompt)
+                .items(&variable.choices)
+                .default(default)
+                .interact()?;
+            Ok(variable.choices[selection].clone())
+        }
+        VariableKind::String => {
+            let mut input = dialoguer::Input::new();
+            input.with_prompt(&variable.prompt);
+            if let Some(default) = &variable.default {
+                input.default(default.clone());
+            }
+            Ok(input.interact_text()?)
+        }
+    }
+}
+
+/// Parse a `--define name=value` argument into its constituent parts.
+pub fn parse_define(input: &str) -> Result<(String, String), Error> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::InvalidDefine(input.to_string()))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Run a template directory's `pre` or `post` hooks in order, injecting `AOC_YEAR`, `AOC_DAY`,
+/// `AOC_DAY_DIR`, and one `AOC_VAR_<NAME>` per resolved template variable. Each entry is a shell
+/// command line (run via `sh -c`, with `template_dir` prepended to `PATH` so a same-named script
+/// there is found too), so both plain commands like `cargo fmt` and checked-in scripts work.
+/// Aborts on the first hook that fails to start or that exits unsuccessfully.
+fn run_hooks(
+    when: &'static str,
+    hooks: &[String],
+    template_dir: &Path,
+    day_dir: &Path,
+    year: u32,
+    day: u8,
+    context: &serde_json::Value,
+) -> Result<(), Error> {
+    for hook in hooks {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(hook);
+        command
+            .current_dir(day_dir)
+            .env("AOC_YEAR", year.to_string())
+            .env("AOC_DAY", day.to_string())
+            .env("AOC_DAY_DIR", day_dir);
+        if let Ok(path) = std::env::var("PATH") {
+            command.env(
+                "PATH",
+                format!("{}:{}", template_dir.display(), path),
+            );
+        }
+        if let Some(variables) = context.as_object() {
+            for (name, value) in variables {
+                let value = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    other => other.to_string(),
+                };
+                command.env(format!("AOC_VAR_{}", name.to_uppercase()), value);
+            }
+        }
+
+        let status = command
+            .status()
+            .map_err(|err| Error::Hook(when, hook.clone(), err))?;
+        if !status.success() {
+            Err(Error::HookFailed(when, hook.clone(), status.code()))?;
+        }
+    }
+    Ok(())
+}
+
 fn render_templates_into(
     config: &Config,
     day_dir: &Path,
     year: u32,
     day: u8,
     day_name: &str,
+    template_source: Option<&str>,
+    defines: &[(String, String)],
+    with_bench: bool,
 ) -> Result<(), Error> {
-    #[derive(Serialize)]
-    struct Context {
-        day: u8,
-        package_name: String,
-    }
-
-    let context = Context {
+    // render templates
+    let template_dir = ensure_template_dir(config, year, template_source)?;
+    let manifest = load_template_manifest(&template_dir)?;
+    let variables = resolve_variables(&manifest, defines)?;
+
+    let mut context = serde_json::Map::new();
+    context.insert("day".to_string(), serde_json::Value::from(day));
+    context.insert(
+        "package_name".to_string(),
+        serde_json::Value::String(day_name.to_string()),
+    );
+    context.extend(variables);
+    let context = serde_json::Value::Object(context);
+
+    run_hooks(
+        "pre",
+        &manifest.pre,
+        &template_dir,
+        day_dir,
+        year,
         day,
-        package_name: day_name.to_string(),
-    };
+        &context,
+    )?;
 
-    // render templates
-    let template_dir = ensure_template_dir(config, year)?;
-    for template in TEMPLATE_FILES {
+    for template in &manifest.files {
         let mut tt = TinyTemplate::new();
-        let template_text = std::fs::read_to_string(template_dir.join(template))?;
+        let source_file = if with_bench && template == "src/main.rs" {
+            BENCH_MAIN_TEMPLATE
+        } else {
+            template.as_str()
+        };
+        let template_text = std::fs::read_to_string(template_dir.join(source_file))?;
         tt.add_template(template, &template_text)
-            .map_err(|err| Error::Template(err, template.to_string()))?;
+            .map_err(|err| Error::Template(err, template.clone()))?;
         let rendered_text = tt
             .render(template, &context)
-            .map_err(|err| Error::Template(err, template.to_string()))?;
+            .map_err(|err| Error::Template(err, template.clone()))?;
 
+        let file_path = day_dir.join(template);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
         let mut file = std::fs::OpenOptions::new()
             .write(true)
             .create_new(true)
-            .open(day_dir.join(template))?;
+            .open(file_path)?;
         file.write_all(rendered_text.as_bytes())?;
     }
 
+    run_hooks(
+        "post",
+        &manifest.post,
+        &template_dir,
+        day_dir,
+        year,
+        day,
+        &context,
+    )?;
+
     Ok(())
 }
 
@@ -147,11 +426,15 @@ fn render_templates_into(
 /// - copying in a few templates to set up the day
 /// - downloading the puzzle input
 pub fn initialize(
-    config: &Config,
+    config: &mut Config,
     year: u32,
     day: u8,
     skip_create_crate: bool,
     skip_get_input: bool,
+    template_source: Option<&str>,
+    defines: &[(String, String)],
+    with_bench: bool,
+    session_override: Option<&str>,
 ) -> Result<(), Error> {
     let implementation_dir = config.implementation(year);
     let (cargo_toml_path, mut manifest) = get_cargo_toml(config, year)?;
@@ -166,10 +449,25 @@ pub fn initialize(
         add_crate_to_workspace(&cargo_toml_path, &mut manifest, &day_name)?;
 
         // render templates, creating new sub-crate
-        render_templates_into(config, &day_dir, year, day, &day_name)?;
+        render_templates_into(
+            config,
+            &day_dir,
+            year,
+            day,
+            &day_name,
+            template_source,
+            defines,
+            with_bench,
+        )?;
     }
 
     if !skip_get_input {
+        // resolve the session token up front, so a missing one is reported clearly here rather
+        // than failing deep inside the HTTP layer
+        if config.session.is_none() {
+            config.session = Some(resolve_session_token(session_override)?);
+        }
+
         // download the input
         aoclib::website::get_input(config, year, day)?;
     }
@@ -186,7 +484,14 @@ pub fn initialize(
 /// - If implementation directory does not exist, create a rust project there.
 /// - Ensure the inputs directory exists.
 /// - Ensure the inputs directory is present in `"$implementation/.gitignore"`
-pub fn initialize_year(config: &mut Config, year: u32, path_opts: PathOpts) -> Result<(), Error> {
+/// - Opportunistically resolve and store the AoC session token, if not already configured and one
+///   is available; since this step does no network I/O, a missing token does not fail it
+pub fn initialize_year(
+    config: &mut Config,
+    year: u32,
+    path_opts: PathOpts,
+    session_override: Option<&str>,
+) -> Result<(), Error> {
     {
         // ensure all specified paths exist and are configured appropriately.
         let ensure_path = |maybe_path: Option<PathBuf>,
@@ -214,6 +519,13 @@ pub fn initialize_year(config: &mut Config, year: u32, path_opts: PathOpts) -> R
         ensure_path(path_opts.day_templates, &mut paths.day_template)?;
     }
 
+    // opportunistically pick up a session token now, if one is available; `initialize_year` does
+    // no network I/O itself, so a user with no token configured yet shouldn't be blocked here —
+    // `initialize` resolves (and fails loudly on) a missing token when it actually needs one
+    if config.session.is_none() {
+        config.session = resolve_session_token(session_override).ok();
+    }
+
     let impl_path = config.implementation(year);
 
     // Create a new Rust project as required.
@@ -254,6 +566,146 @@ pub fn initialize_year(config: &mut Config, year: u32, path_opts: PathOpts) -> R
     Ok(())
 }
 
+/// Resolve the AoC session token, in precedence order: an explicit CLI override, the
+/// `AOC_SESSION` environment variable, then a `.env` file in the working directory. Returns
+/// `Error::NoSessionToken` with actionable guidance if none of those provide a token, rather than
+/// letting the HTTP layer fail deep inside with an opaque auth error.
+pub fn resolve_session_token(cli_override: Option<&str>) -> Result<String, Error> {
+    if let Some(token) = cli_override {
+        return Ok(token.to_string());
+    }
+    dotenv::dotenv().ok();
+    std::env::var("AOC_SESSION").map_err(|_| Error::NoSessionToken)
+}
+
+/// Resolve a default year, in the same precedence order as `resolve_session_token`, using
+/// `AOC_YEAR`. Returns `None` if no source provides one, leaving the caller to fall back to a
+/// required CLI argument.
+pub fn resolve_default_year(cli_override: Option<u32>) -> Option<u32> {
+    if cli_override.is_some() {
+        return cli_override;
+    }
+    dotenv::dotenv().ok();
+    std::env::var("AOC_YEAR")
+        .ok()
+        .and_then(|year| year.parse().ok())
+}
+
+fn workspace_days(manifest: &Document) -> Vec<u8> {
+    let members = manifest
+        .root
+        .as_table()
+        .and_then(|root| root.get("workspace"))
+        .and_then(|workspace| workspace.as_table())
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_value())
+        .and_then(|members| members.as_array());
+
+    let mut days: Vec<u8> = members
+        .into_iter()
+        .flatten()
+        .filter_map(|member| member.as_str())
+        .filter_map(|member| member.strip_prefix("day"))
+        .filter_map(|day| day.parse().ok())
+        .collect();
+    days.sort_unstable();
+    days
+}
+
+/// Whether the function starting at `signature` (and ending at `next_signature`, or EOF if
+/// `None`) has been implemented, i.e. no longer contains `unimplemented!`. Returns `false` if
+/// `signature` is not found at all.
+fn part_implemented(lib_rs: &str, signature: &str, next_signature: Option<&str>) -> bool {
+    let body = match lib_rs.find(signature) {
+        Some(start) => match next_signature.and_then(|next| lib_rs[start..].find(next)) {
+            Some(len) => &lib_rs[start..start + len],
+            None => &lib_rs[start..],
+        },
+        None => return false,
+    };
+    !body.contains("unimplemented!")
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayStatus {
+    pub day: u8,
+    pub crate_exists: bool,
+    pub input_downloaded: bool,
+    pub part1_implemented: bool,
+    pub part2_implemented: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct YearStatus {
+    pub year: u32,
+    pub days: Vec<DayStatus>,
+}
+
+/// Report, for each day already scaffolded into `year`'s workspace, whether its crate exists on
+/// disk, whether its input has been downloaded, and whether `part1`/`part2` are still
+/// `unimplemented!`.
+///
+/// Like rust-analyzer deriving a project model from `cargo metadata`, this walks the parsed
+/// workspace manifest (the same `workspace.members` array `add_crate_to_workspace` edits) rather
+/// than guessing at scaffolded days from the filesystem alone.
+pub fn status(config: &Config, year: u32) -> Result<YearStatus, Error> {
+    let (_, manifest) = get_cargo_toml(config, year)?;
+    let implementation_dir = config.implementation(year);
+
+    let days = workspace_days(&manifest)
+        .into_iter()
+        .map(|day| {
+            let day_dir = implementation_dir.join(format!("day{:02}", day));
+            let lib_rs = std::fs::read_to_string(day_dir.join("src/lib.rs")).unwrap_or_default();
+
+            DayStatus {
+                day,
+                crate_exists: day_dir.exists(),
+                // same path `aoclib::website::get_input` writes to, rather than a hand-rolled
+                // guess at its naming convention
+                input_downloaded: config.input(year, day).exists(),
+                part1_implemented: part_implemented(&lib_rs, "pub fn part1", Some("pub fn part2")),
+                part2_implemented: part_implemented(&lib_rs, "pub fn part2", None),
+            }
+        })
+        .collect();
+
+    Ok(YearStatus { year, days })
+}
+
+/// Print a `YearStatus` either as a human-readable table or, if `json` is set, as pretty-printed
+/// JSON suitable for scripting.
+pub fn print_status(status: &YearStatus, json: bool) -> Result<(), Error> {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(status).map_err(Error::SerializeStatus)?
+        );
+        return Ok(());
+    }
+
+    println!("day  crate  input  part1  part2");
+    for day in &status.days {
+        println!(
+            "{:02}   {:<5}  {:<5}  {:<5}  {:<5}",
+            day.day,
+            done(day.crate_exists),
+            done(day.input_downloaded),
+            done(day.part1_implemented),
+            done(day.part2_implemented),
+        );
+    }
+    Ok(())
+}
+
+fn done(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error(transparent)]
@@ -280,6 +732,26 @@ pub enum Error {
     ResponseStatus(#[source] reqwest::Error),
     #[error("downloading day template to local file")]
     Downloading(#[source] reqwest::Error),
+    #[error("template `{0}` missing from the embedded default day template")]
+    MissingEmbeddedTemplate(String),
+    #[error("could not parse template.toml")]
+    ParseTemplateManifest(#[source] toml::de::Error),
+    #[error("`--define` value `{0}` is not of the form name=value")]
+    InvalidDefine(String),
+    #[error("invalid validation regex for variable `{0}`")]
+    InvalidVariableRegex(String, #[source] regex::Error),
+    #[error("value `{1}` for variable `{0}` failed its validation regex")]
+    VariableValidation(String, String),
+    #[error("variable `{0}` is declared as type = \"choice\" but has no choices")]
+    EmptyChoiceVariable(String),
+    #[error("{0} hook `{1}` could not be run")]
+    Hook(&'static str, String, #[source] std::io::Error),
+    #[error("{0} hook `{1}` exited with status {2:?}")]
+    HookFailed(&'static str, String, Option<i32>),
+    #[error("failed to serialize status to JSON")]
+    SerializeStatus(#[source] serde_json::Error),
+    #[error("no AoC session token found; pass --session, set AOC_SESSION, or add AOC_SESSION=... to a .env file")]
+    NoSessionToken,
 }
 
 #[derive(StructOpt, Debug)]